@@ -0,0 +1,14 @@
+pub(crate) const TUS_RESUMABLE: &str = "tus-resumable";
+pub(crate) const TUS_VERSION: &str = "tus-version";
+pub(crate) const TUS_EXTENSION: &str = "tus-extension";
+pub(crate) const TUS_MAX_SIZE: &str = "tus-max-size";
+pub(crate) const UPLOAD_OFFSET: &str = "upload-offset";
+pub(crate) const UPLOAD_LENGTH: &str = "upload-length";
+pub(crate) const UPLOAD_CHECKSUM: &str = "upload-checksum";
+pub(crate) const EXPECT: &str = "expect";
+pub(crate) const LOCATION: &str = "location";
+pub(crate) const UPLOAD_METADATA: &str = "upload-metadata";
+pub(crate) const UPLOAD_EXPIRES: &str = "upload-expires";
+pub(crate) const UPLOAD_DEFER_LENGTH: &str = "upload-defer-length";
+pub(crate) const CONTENT_TYPE: &str = "content-type";
+pub(crate) const X_HTTP_METHOD_OVERRIDE: &str = "x-http-method-override";
@@ -0,0 +1,76 @@
+use crate::headers;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+pub type Headers = HashMap<String, String>;
+
+/// Headers that should be sent with every request, regardless of method.
+pub fn default_headers() -> Headers {
+    let mut headers = Headers::new();
+    headers.insert(headers::TUS_RESUMABLE.to_owned(), "1.0.0".to_owned());
+    headers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Head,
+    Patch,
+    Delete,
+    Options,
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Options => "OPTIONS",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct HttpRequest<'a> {
+    pub method: HttpMethod,
+    pub url: String,
+    pub body: Option<&'a [u8]>,
+    pub headers: Headers,
+}
+
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status_code: usize,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+/// What happened after sending just the headers of an `Expect: 100-continue` request.
+pub enum ExpectOutcome {
+    /// The server is ready for the body; send it.
+    Proceed,
+    /// The server settled the request from the headers alone; don't send the body.
+    Settled(HttpResponse),
+}
+
+/// Implemented by the HTTP client the caller wants the [`Client`](crate::Client) to drive
+/// requests through, so this crate stays agnostic of any particular HTTP library.
+pub trait HttpHandler {
+    fn handle_request(&self, request: HttpRequest) -> Result<HttpResponse, io::Error>;
+
+    /// Send only `request`'s headers and report the server's interim response.
+    /// Defaults to always proceeding, for handlers that can't hold the body back.
+    fn send_expect_headers(&self, _request: &HttpRequest) -> Result<ExpectOutcome, io::Error> {
+        Ok(ExpectOutcome::Proceed)
+    }
+
+    /// Send `request` in full after [`send_expect_headers`] returned `Proceed`.
+    fn send_body(&self, request: HttpRequest) -> Result<HttpResponse, io::Error> {
+        self.handle_request(request)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::http::{default_headers, Headers, HttpHandler, HttpMethod, HttpRequest};
+use crate::http::{
+    default_headers, ExpectOutcome, Headers, HttpHandler, HttpMethod, HttpRequest, HttpResponse,
+};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io;
@@ -7,14 +10,24 @@ use std::num::ParseIntError;
 use std::ops::Deref;
 use std::path::Path;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
 mod headers;
 pub mod http;
 
 const DEFAULT_CHUNK_SIZE: usize = 5 * 1024 * 1024;
+const MAX_CHECKSUM_RETRIES: u32 = 3;
+
+/// A `FnMut(bytes_uploaded, total_size)` hook invoked after each chunk the server
+/// confirms with a `204`, so callers can render a progress bar.
+type ProgressCallback<'a> = dyn FnMut(usize, Option<usize>) + 'a;
 
 pub struct Client<'a> {
     use_method_override: bool,
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+    strict_extensions: bool,
+    retry_policy: RetryPolicy,
+    progress_callback: RefCell<Option<Box<ProgressCallback<'a>>>>,
     http_handler: Box<dyn HttpHandler + 'a>,
 }
 
@@ -22,6 +35,10 @@ impl<'a> Client<'a> {
     pub fn new(http_handler: impl HttpHandler + 'a) -> Self {
         Client {
             use_method_override: false,
+            checksum_algorithm: None,
+            strict_extensions: false,
+            retry_policy: RetryPolicy::default(),
+            progress_callback: RefCell::new(None),
             http_handler: Box::new(http_handler),
         }
     }
@@ -29,10 +46,48 @@ impl<'a> Client<'a> {
     pub fn with_method_override(http_handler: impl HttpHandler + 'a) -> Self {
         Client {
             use_method_override: true,
+            checksum_algorithm: None,
+            strict_extensions: false,
+            retry_policy: RetryPolicy::default(),
+            progress_callback: RefCell::new(None),
             http_handler: Box::new(http_handler),
         }
     }
 
+    /// Attach an `Upload-Checksum` header to every PATCH chunk, computed over exactly the
+    /// bytes in that chunk, and retry the chunk if the server reports `460 Checksum Mismatch`.
+    pub fn with_checksum(self, algorithm: ChecksumAlgorithm) -> Self {
+        Client {
+            checksum_algorithm: Some(algorithm),
+            ..self
+        }
+    }
+
+    /// When set, uploads fail with [`Error::ExtensionNotSupported`] instead of silently
+    /// skipping a requested extension the server didn't advertise in `get_server_info`.
+    pub fn require_extension_support(mut self) -> Self {
+        self.strict_extensions = true;
+        self
+    }
+
+    /// Register a callback invoked as `callback(bytes_uploaded, total_size)` after every
+    /// chunk the server confirms with a `204`.
+    pub fn with_progress_callback(self, callback: impl FnMut(usize, Option<usize>) + 'a) -> Self {
+        Client {
+            progress_callback: RefCell::new(Some(Box::new(callback))),
+            ..self
+        }
+    }
+
+    /// Replace the default [`RetryPolicy`] used to resync and retry a chunk after a
+    /// transient `HttpHandler` error or a `409`.
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Client {
+            retry_policy,
+            ..self
+        }
+    }
+
     /// Get the number of bytes already uploaded to the server
     pub fn get_progress(&self, url: &str) -> Result<ProgressResponse, Error> {
         let req = self.create_request(HttpMethod::Head, url, None, Some(default_headers()));
@@ -50,13 +105,32 @@ impl<'a> Client<'a> {
         }
 
         let bytes_uploaded = bytes_uploaded.unwrap().parse()?;
+        let expires_at = parse_upload_expires(&response);
 
         Ok(ProgressResponse {
             bytes_uploaded,
             total_size,
+            expires_at,
         })
     }
 
+    /// End an upload via the tus `Termination` extension, telling the server it can
+    /// discard any bytes already received.
+    pub fn terminate(&self, url: &str) -> Result<(), Error> {
+        let req = self.create_request(HttpMethod::Delete, url, None, Some(default_headers()));
+
+        let response = self.http_handler.deref().handle_request(req)?;
+
+        match response.status_code {
+            204 => Ok(()),
+            404 | 410 => Err(Error::NotFoundError),
+            _ => Err(Error::BadResponse(format!(
+                "Expected response status code to be '204', but received '{}'",
+                response.status_code
+            ))),
+        }
+    }
+
     pub fn upload(&self, url: &str, path: &Path) -> Result<(), Error> {
         self.upload_with_chunk_size(url, path, DEFAULT_CHUNK_SIZE)
     }
@@ -69,73 +143,233 @@ impl<'a> Client<'a> {
     ) -> Result<(), Error> {
         let progress = self.get_progress(url)?;
         let mut file = File::open(path)?;
-        let file_len = file.metadata()?.len();
+        let file_len = file.metadata()?.len() as usize;
 
         if let Some(total_size) = progress.total_size {
-            if file_len as usize != total_size {
+            if file_len != total_size {
                 return Err(Error::UnequalSizeError);
             }
         }
 
-        let mut buffer = vec![0; chunk_size];
-        let mut progress = progress.bytes_uploaded;
+        if let Some(expires_at) = progress.expires_at {
+            if SystemTime::now() > expires_at {
+                return Err(Error::UploadExpired);
+            }
+        }
 
-        file.seek(SeekFrom::Start(progress as u64))?;
+        file.seek(SeekFrom::Start(progress.bytes_uploaded as u64))?;
+
+        self.upload_reader_from(url, file, chunk_size, progress.bytes_uploaded, Some(file_len))
+    }
+
+    /// Upload an arbitrary [`Read`] source to `url`, without seeking and without the
+    /// total length known up front; the final chunk carries `Upload-Length` once `reader` is exhausted.
+    pub fn upload_reader(
+        &self,
+        url: &str,
+        mut reader: impl Read,
+        chunk_size: usize,
+    ) -> Result<(), Error> {
+        let progress = self.get_progress(url)?;
+
+        if let Some(expires_at) = progress.expires_at {
+            if SystemTime::now() > expires_at {
+                return Err(Error::UploadExpired);
+            }
+        }
+
+        if progress.bytes_uploaded > 0 {
+            skip_bytes(&mut reader, progress.bytes_uploaded)?;
+        }
+
+        self.upload_reader_from(url, reader, chunk_size, progress.bytes_uploaded, None)
+    }
+
+    /// Shared chunking/upload loop behind [`upload_with_chunk_size`](Client::upload_with_chunk_size)
+    /// and [`upload_reader`](Client::upload_reader); `known_len` is `None` for a deferred-length stream.
+    fn upload_reader_from(
+        &self,
+        url: &str,
+        mut reader: impl Read,
+        chunk_size: usize,
+        mut progress: usize,
+        known_len: Option<usize>,
+    ) -> Result<(), Error> {
+        let send_checksum = match &self.checksum_algorithm {
+            None => false,
+            Some(_) => {
+                let server_info = self.get_server_info(url)?;
+                let supported = server_info.extensions.contains(&TusExtension::Checksum);
+                if !supported && self.strict_extensions {
+                    return Err(Error::ExtensionNotSupported(TusExtension::Checksum));
+                }
+                supported
+            }
+        };
+
+        let mut buffer = vec![0; chunk_size];
+        // A byte read ahead of the current chunk to probe for EOF; see below.
+        let mut pending_byte: Option<u8> = None;
+        let mut is_first_chunk = true;
 
         loop {
             let mut bytes_loaded = 0;
+            if let Some(byte) = pending_byte.take() {
+                buffer[0] = byte;
+                bytes_loaded = 1;
+            }
+
             loop {
                 let mut end = bytes_loaded + 8000;
                 if end > chunk_size {
                     end = chunk_size;
                 }
 
-                let bytes_read = file.read(&mut buffer[bytes_loaded..end])?;
+                let bytes_read = reader.read(&mut buffer[bytes_loaded..end])?;
                 bytes_loaded += bytes_read;
 
                 if bytes_read == 0 || end == chunk_size {
                     break;
                 }
             }
-            if buffer.is_empty() {
-                return Err(Error::FileReadError);
-            }
-
-            let req = self.create_request(
-                HttpMethod::Patch,
-                url,
-                Some(&buffer[..bytes_loaded]),
-                Some(create_upload_headers(progress)),
-            );
-
-            let response = self.http_handler.deref().handle_request(req)?;
 
-            if response.status_code == 409 {
-                return Err(Error::WrongUploadOffsetError);
+            if bytes_loaded == 0 {
+                // A genuinely empty deferred-length stream still needs one PATCH
+                // declaring Upload-Length: 0, or the upload is stuck forever in
+                // Upload-Defer-Length state. Anywhere past the first chunk, an empty
+                // read here means the previous chunk already finished the upload.
+                if !is_first_chunk || known_len.is_some() {
+                    break;
+                }
             }
+            is_first_chunk = false;
 
-            if response.status_code == 404 {
-                return Err(Error::NotFoundError);
+            // A chunk that filled the whole buffer isn't necessarily non-final: if the
+            // stream's true length is an exact multiple of chunk_size, this is the last
+            // chunk and reader is now at EOF. Probe one byte ahead to find out, carrying
+            // it over to the next chunk if the stream isn't actually done yet.
+            let mut is_final_chunk = bytes_loaded < chunk_size;
+            if !is_final_chunk {
+                let mut probe = [0u8; 1];
+                if reader.read(&mut probe)? == 0 {
+                    is_final_chunk = true;
+                } else {
+                    pending_byte = Some(probe[0]);
+                }
             }
 
-            if response.status_code != 204 {
-                return Err(Error::BadResponse(format!(
-                    "Expected response status code to be '204', but received '{}'",
-                    response.status_code
-                )));
+            let mut headers = create_upload_headers(progress);
+            headers.insert(headers::EXPECT.to_owned(), "100-continue".to_owned());
+            if known_len.is_none() && is_final_chunk {
+                headers.insert(
+                    headers::UPLOAD_LENGTH.to_owned(),
+                    (progress + bytes_loaded).to_string(),
+                );
             }
 
-            let upload_offset = match response.headers.get_by_key(headers::UPLOAD_OFFSET) {
-                Some(offset) => Ok(offset),
-                None => Err(Error::BadResponse(format!(
-                    "'{}' header missing from response",
-                    headers::UPLOAD_OFFSET
-                ))),
-            }?;
+            // The offset and (re-sliced) body the next PATCH attempt should send; moves
+            // forward within this already-buffered chunk if a resync reveals the server
+            // received part of it despite a transient error or a 409.
+            let mut send_offset = progress;
+            let mut send_start = 0;
+            let mut checksum_retries = 0;
+            let mut transient_retries = 0;
 
-            progress = upload_offset.parse()?;
+            let upload_offset = loop {
+                // `bytes_loaded == 0` only happens for the one allowed empty-chunk PATCH
+                // below (used to declare Upload-Length: 0); that one still needs sending.
+                if bytes_loaded > 0 && send_start >= bytes_loaded {
+                    break send_offset;
+                }
+
+                let body = &buffer[send_start..bytes_loaded];
+
+                let mut req_headers = headers.clone();
+                req_headers.insert(headers::UPLOAD_OFFSET.to_owned(), send_offset.to_string());
+                if send_checksum {
+                    let algorithm = self.checksum_algorithm.as_ref().unwrap();
+                    req_headers.insert(
+                        headers::UPLOAD_CHECKSUM.to_owned(),
+                        format!("{} {}", algorithm.name(), base64::encode(algorithm.digest(body))),
+                    );
+                }
+
+                let req = self.create_request(HttpMethod::Patch, url, Some(body), Some(req_headers));
+
+                let result = match self.http_handler.deref().send_expect_headers(&req) {
+                    Ok(ExpectOutcome::Settled(response)) => Ok(response),
+                    Ok(ExpectOutcome::Proceed) => self.http_handler.deref().send_body(req),
+                    Err(e) => Err(e),
+                };
+
+                let response = match result {
+                    Ok(response) => response,
+                    Err(_io_err) => {
+                        transient_retries += 1;
+                        if transient_retries > self.retry_policy.max_retries {
+                            return Err(Error::RetriesExhausted(transient_retries));
+                        }
+                        std::thread::sleep(self.retry_policy.backoff(transient_retries));
+                        resync_chunk(self, url, progress, bytes_loaded, &mut send_offset, &mut send_start)?;
+                        continue;
+                    }
+                };
+
+                if response.status_code == 460 {
+                    checksum_retries += 1;
+                    if checksum_retries > MAX_CHECKSUM_RETRIES {
+                        return Err(Error::ChecksumMismatch);
+                    }
+                    continue;
+                }
+
+                if response.status_code == 409 {
+                    transient_retries += 1;
+                    if transient_retries > self.retry_policy.max_retries {
+                        return Err(Error::RetriesExhausted(transient_retries));
+                    }
+                    std::thread::sleep(self.retry_policy.backoff(transient_retries));
+                    resync_chunk(self, url, progress, bytes_loaded, &mut send_offset, &mut send_start)?;
+                    continue;
+                }
+
+                if response.status_code == 404 {
+                    return Err(Error::NotFoundError);
+                }
+
+                if response.status_code != 204 {
+                    return Err(Error::BadResponse(format!(
+                        "Expected response status code to be '204', but received '{}'",
+                        response.status_code
+                    )));
+                }
 
-            if progress >= file_len as usize {
+                if let Some(expires_at) = parse_upload_expires(&response) {
+                    if SystemTime::now() > expires_at {
+                        return Err(Error::UploadExpired);
+                    }
+                }
+
+                break match response.headers.get_by_key(headers::UPLOAD_OFFSET) {
+                    Some(offset) => offset.parse::<usize>().map_err(Error::from),
+                    None => Err(Error::BadResponse(format!(
+                        "'{}' header missing from response",
+                        headers::UPLOAD_OFFSET
+                    ))),
+                }?;
+            };
+
+            progress = upload_offset;
+
+            if let Some(callback) = self.progress_callback.borrow_mut().as_mut() {
+                callback(progress, known_len);
+            }
+
+            let done = match known_len {
+                Some(len) => progress >= len,
+                None => is_final_chunk,
+            };
+            if done {
                 break;
             }
         }
@@ -143,6 +377,99 @@ impl<'a> Client<'a> {
         Ok(())
     }
 
+    /// Create a new upload on the server via the tus `Creation` extension, returning the
+    /// `Location` URL the file's chunks should subsequently be `upload`ed to.
+    pub fn create_upload(
+        &self,
+        creation_url: &str,
+        file_len: usize,
+        metadata: HashMap<String, String>,
+    ) -> Result<String, Error> {
+        let mut headers = default_headers();
+        headers.insert(headers::UPLOAD_LENGTH.to_owned(), file_len.to_string());
+        if !metadata.is_empty() {
+            headers.insert(
+                headers::UPLOAD_METADATA.to_owned(),
+                encode_upload_metadata(&metadata),
+            );
+        }
+
+        let req = self.create_request(HttpMethod::Post, creation_url, None, Some(headers));
+        let response = self.http_handler.deref().handle_request(req)?;
+
+        creation_location(&response)
+    }
+
+    /// Like [`create_upload`](Client::create_upload), but sends `Upload-Defer-Length: 1`
+    /// for servers advertising `creation-defer-length`; pair with [`upload_reader`](Client::upload_reader).
+    pub fn create_upload_deferred(
+        &self,
+        creation_url: &str,
+        metadata: HashMap<String, String>,
+    ) -> Result<String, Error> {
+        let mut headers = default_headers();
+        headers.insert(headers::UPLOAD_DEFER_LENGTH.to_owned(), "1".to_owned());
+        if !metadata.is_empty() {
+            headers.insert(
+                headers::UPLOAD_METADATA.to_owned(),
+                encode_upload_metadata(&metadata),
+            );
+        }
+
+        let req = self.create_request(HttpMethod::Post, creation_url, None, Some(headers));
+        let response = self.http_handler.deref().handle_request(req)?;
+
+        creation_location(&response)
+    }
+
+    /// Like [`create_upload`](Client::create_upload), but appends `initial_chunk` to the
+    /// creation `POST` body per `creation-with-upload`, returning the bytes the server confirmed.
+    pub fn create_upload_with_data(
+        &self,
+        creation_url: &str,
+        file_len: usize,
+        metadata: HashMap<String, String>,
+        initial_chunk: &[u8],
+    ) -> Result<(String, usize), Error> {
+        let mut headers = default_headers();
+        headers.insert(headers::UPLOAD_LENGTH.to_owned(), file_len.to_string());
+        headers.insert(
+            headers::CONTENT_TYPE.to_owned(),
+            "application/offset+octet-stream".to_owned(),
+        );
+        if !metadata.is_empty() {
+            headers.insert(
+                headers::UPLOAD_METADATA.to_owned(),
+                encode_upload_metadata(&metadata),
+            );
+        }
+
+        let req =
+            self.create_request(HttpMethod::Post, creation_url, Some(initial_chunk), Some(headers));
+        let response = self.http_handler.deref().handle_request(req)?;
+        let location = creation_location(&response)?;
+
+        let bytes_uploaded = match response.headers.get_by_key(headers::UPLOAD_OFFSET) {
+            Some(offset) => offset.parse()?,
+            None => 0,
+        };
+
+        Ok((location, bytes_uploaded))
+    }
+
+    /// Create a new upload and immediately upload `path` to it, returning the upload URL.
+    pub fn create_and_upload(
+        &self,
+        creation_url: &str,
+        path: &Path,
+        metadata: HashMap<String, String>,
+    ) -> Result<String, Error> {
+        let file_len = path.metadata()?.len() as usize;
+        let upload_url = self.create_upload(creation_url, file_len, metadata)?;
+        self.upload(&upload_url, path)?;
+        Ok(upload_url)
+    }
+
     /// Get information about the tus server
     pub fn get_server_info(&self, url: &str) -> Result<ServerInfo, Error> {
         let req = self.create_request(HttpMethod::Options, url, None, None);
@@ -165,11 +492,7 @@ impl<'a> Client<'a> {
             .collect();
         let extensions: Vec<TusExtension> =
             if let Some(ext) = response.headers.get_by_key(headers::TUS_EXTENSION) {
-                ext.split(',')
-                    .map(str::parse)
-                    .filter(Result::is_ok)
-                    .map(Result::unwrap)
-                    .collect()
+                ext.split(',').flat_map(str::parse).collect()
             } else {
                 Vec::new()
             };
@@ -217,6 +540,8 @@ impl<'a> Client<'a> {
 pub struct ProgressResponse {
     pub bytes_uploaded: usize,
     pub total_size: Option<usize>,
+    /// The `Upload-Expires` deadline the server reported, if the `Expiration` extension is in use.
+    pub expires_at: Option<SystemTime>,
 }
 
 #[derive(Debug)]
@@ -257,8 +582,68 @@ pub enum Error {
     IoError(io::Error),
     ParsingError(ParseIntError),
     UnequalSizeError,
-    FileReadError,
     WrongUploadOffsetError,
+    ChecksumMismatch,
+    ExtensionNotSupported(TusExtension),
+    CreationFailed(String),
+    UploadExpired,
+    RetriesExhausted(u32),
+}
+
+/// Controls retry/backoff after a transient `HttpHandler` error or a `409` on a chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Digest algorithm used for the tus `Checksum` extension.
+#[derive(Debug, PartialEq)]
+pub enum ChecksumAlgorithm {
+    Sha1,
+    Md5,
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha1 => "sha1",
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Crc32 => "crc32",
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            ChecksumAlgorithm::Sha1 => {
+                use sha1::{Digest, Sha1};
+                let mut hasher = Sha1::new();
+                hasher.update(data);
+                hasher.finalize().to_vec()
+            }
+            ChecksumAlgorithm::Md5 => md5::compute(data).to_vec(),
+            ChecksumAlgorithm::Crc32 => crc32fast::hash(data).to_be_bytes().to_vec(),
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -285,6 +670,85 @@ impl HeaderMap for HashMap<String, String> {
     }
 }
 
+/// Builds an `Upload-Metadata` header value: comma-separated `key base64(value)` pairs,
+/// per the tus `Creation` extension.
+fn encode_upload_metadata(metadata: &HashMap<String, String>) -> String {
+    metadata
+        .iter()
+        .map(|(key, value)| format!("{} {}", key, base64::encode(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Checks a creation `POST` response for `201` and extracts its `Location` header.
+fn creation_location(response: &HttpResponse) -> Result<String, Error> {
+    if response.status_code != 201 {
+        return Err(Error::CreationFailed(format!(
+            "Expected response status code to be '201', but received '{}'",
+            response.status_code
+        )));
+    }
+
+    match response.headers.get_by_key(headers::LOCATION) {
+        Some(location) => Ok(location.clone()),
+        None => Err(Error::CreationFailed(format!(
+            "'{}' header missing from response",
+            headers::LOCATION
+        ))),
+    }
+}
+
+/// Re-syncs a retried chunk against the server's real offset, advancing `send_start`/
+/// `send_offset` to resend only the remainder still unsent.
+fn resync_chunk(
+    client: &Client<'_>,
+    url: &str,
+    progress: usize,
+    bytes_loaded: usize,
+    send_offset: &mut usize,
+    send_start: &mut usize,
+) -> Result<(), Error> {
+    let resynced = client.get_progress(url)?;
+
+    if let Some(expires_at) = resynced.expires_at {
+        if SystemTime::now() > expires_at {
+            return Err(Error::UploadExpired);
+        }
+    }
+
+    if resynced.bytes_uploaded < progress {
+        return Err(Error::WrongUploadOffsetError);
+    }
+
+    *send_offset = resynced.bytes_uploaded;
+    *send_start = (resynced.bytes_uploaded - progress).min(bytes_loaded);
+    Ok(())
+}
+
+/// Reads and discards `count` bytes from a non-seekable reader, used to fast-forward
+/// past bytes the server already has when resuming an [`upload_reader`](Client::upload_reader).
+fn skip_bytes(reader: &mut impl Read, mut count: usize) -> io::Result<()> {
+    let mut discard = [0u8; 8000];
+    while count > 0 {
+        let to_read = discard.len().min(count);
+        let bytes_read = reader.read(&mut discard[..to_read])?;
+        if bytes_read == 0 {
+            break;
+        }
+        count -= bytes_read;
+    }
+    Ok(())
+}
+
+/// Parses the `Upload-Expires` header (an RFC 7231 HTTP-date) from a response, if the
+/// server sent one.
+fn parse_upload_expires(response: &HttpResponse) -> Option<SystemTime> {
+    response
+        .headers
+        .get_by_key(headers::UPLOAD_EXPIRES)
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+}
+
 fn create_upload_headers(progress: usize) -> Headers {
     let mut headers = default_headers();
     headers.insert(
@@ -294,3 +758,348 @@ fn create_upload_headers(progress: usize) -> Headers {
     headers.insert(headers::UPLOAD_OFFSET.to_owned(), progress.to_string());
     headers
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    struct FakeHandler {
+        responses: RefCell<Vec<(HttpMethod, VecDeque<HttpResponse>)>>,
+        requests: RefCell<Vec<(HttpMethod, Headers)>>,
+        settle_expect: RefCell<Option<HttpResponse>>,
+        expect_header_calls: RefCell<Vec<HttpMethod>>,
+    }
+
+    impl FakeHandler {
+        fn new() -> Self {
+            FakeHandler {
+                responses: RefCell::new(Vec::new()),
+                requests: RefCell::new(Vec::new()),
+                settle_expect: RefCell::new(None),
+                expect_header_calls: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn script(&self, method: HttpMethod, response: HttpResponse) {
+            let mut responses = self.responses.borrow_mut();
+            match responses.iter_mut().find(|(m, _)| *m == method) {
+                Some((_, queue)) => queue.push_back(response),
+                None => responses.push((method, VecDeque::from([response]))),
+            }
+        }
+
+        /// Make the next `send_expect_headers` call short-circuit with `response`
+        /// instead of proceeding to `send_body`.
+        fn settle_next_expect_with(&self, response: HttpResponse) {
+            *self.settle_expect.borrow_mut() = Some(response);
+        }
+
+        fn respond(&self, request: HttpRequest) -> Result<HttpResponse, io::Error> {
+            self.requests
+                .borrow_mut()
+                .push((request.method, request.headers.clone()));
+            let mut responses = self.responses.borrow_mut();
+            let (_, queue) = responses
+                .iter_mut()
+                .find(|(m, _)| *m == request.method)
+                .unwrap_or_else(|| panic!("no responses scripted for {}", request.method));
+            Ok(queue
+                .pop_front()
+                .unwrap_or_else(|| panic!("scripted responses for {} exhausted", request.method)))
+        }
+
+        fn requests_for(&self, method: HttpMethod) -> Vec<Headers> {
+            self.requests
+                .borrow()
+                .iter()
+                .filter(|(m, _)| *m == method)
+                .map(|(_, h)| h.clone())
+                .collect()
+        }
+    }
+
+    impl HttpHandler for &FakeHandler {
+        fn handle_request(&self, request: HttpRequest) -> Result<HttpResponse, io::Error> {
+            (*self).respond(request)
+        }
+
+        fn send_expect_headers(&self, request: &HttpRequest) -> Result<ExpectOutcome, io::Error> {
+            self.expect_header_calls.borrow_mut().push(request.method);
+            match self.settle_expect.borrow_mut().take() {
+                Some(response) => Ok(ExpectOutcome::Settled(response)),
+                None => Ok(ExpectOutcome::Proceed),
+            }
+        }
+    }
+
+    fn response(status_code: usize, headers: &[(&str, &str)]) -> HttpResponse {
+        let mut map = Headers::new();
+        for (key, value) in headers {
+            map.insert((*key).to_owned(), (*value).to_owned());
+        }
+        HttpResponse {
+            status_code,
+            headers: map,
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn upload_reader_sends_upload_length_once_stream_hits_true_eof() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "8")]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "16")]));
+
+        let client = Client::new(&handler);
+        let data = [1u8; 16];
+        client
+            .upload_reader("http://example.test/upload", &data[..], 8)
+            .unwrap();
+
+        let patches = handler.requests_for(HttpMethod::Patch);
+        assert_eq!(patches.len(), 2);
+        assert!(!patches[0].contains_key(headers::UPLOAD_LENGTH));
+        assert_eq!(patches[1].get(headers::UPLOAD_LENGTH), Some(&"16".to_owned()));
+    }
+
+    #[test]
+    fn retries_chunk_after_checksum_mismatch() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(
+            HttpMethod::Options,
+            response(
+                200,
+                &[
+                    (headers::TUS_VERSION, "1.0.0"),
+                    (headers::TUS_EXTENSION, "checksum"),
+                ],
+            ),
+        );
+        handler.script(HttpMethod::Patch, response(460, &[]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "4")]));
+
+        let client = Client::new(&handler).with_checksum(ChecksumAlgorithm::Sha1);
+        let data = [9u8; 4];
+        client
+            .upload_reader("http://example.test/upload", &data[..], 8)
+            .unwrap();
+
+        assert_eq!(handler.requests_for(HttpMethod::Patch).len(), 2);
+    }
+
+    #[test]
+    fn resyncs_and_retries_after_a_409() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(HttpMethod::Patch, response(409, &[]));
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "4")]));
+
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        };
+        let client = Client::new(&handler).with_retry_policy(policy);
+        let data = [9u8; 4];
+        client
+            .upload_reader("http://example.test/upload", &data[..], 8)
+            .unwrap();
+
+        assert_eq!(handler.requests_for(HttpMethod::Patch).len(), 2);
+        assert_eq!(handler.requests_for(HttpMethod::Head).len(), 2);
+    }
+
+    #[test]
+    fn settled_expect_headers_short_circuit_without_sending_the_body() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.settle_next_expect_with(response(409, &[]));
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "4")]));
+
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        };
+        let client = Client::new(&handler).with_retry_policy(policy);
+        let data = [7u8; 4];
+        client
+            .upload_reader("http://example.test/upload", &data[..], 8)
+            .unwrap();
+
+        // Both attempts sent Expect headers, but only the second (Proceed) one ever
+        // reached handle_request and actually transmitted the chunk's body.
+        let patch_expect_calls = handler
+            .expect_header_calls
+            .borrow()
+            .iter()
+            .filter(|m| **m == HttpMethod::Patch)
+            .count();
+        assert_eq!(patch_expect_calls, 2);
+        assert_eq!(handler.requests_for(HttpMethod::Patch).len(), 1);
+    }
+
+    #[test]
+    fn create_upload_returns_the_location_header() {
+        let handler = FakeHandler::new();
+        handler.script(
+            HttpMethod::Post,
+            response(201, &[(headers::LOCATION, "http://example.test/uploads/1")]),
+        );
+
+        let client = Client::new(&handler);
+        let location = client
+            .create_upload("http://example.test/uploads", 10, HashMap::new())
+            .unwrap();
+
+        assert_eq!(location, "http://example.test/uploads/1");
+    }
+
+    #[test]
+    fn create_upload_errors_when_location_header_is_missing() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Post, response(201, &[]));
+
+        let client = Client::new(&handler);
+        let err = client
+            .create_upload("http://example.test/uploads", 10, HashMap::new())
+            .unwrap_err();
+
+        assert!(matches!(err, Error::CreationFailed(_)));
+    }
+
+    #[test]
+    fn create_upload_deferred_sends_upload_defer_length_instead_of_upload_length() {
+        let handler = FakeHandler::new();
+        handler.script(
+            HttpMethod::Post,
+            response(201, &[(headers::LOCATION, "http://example.test/uploads/1")]),
+        );
+
+        let client = Client::new(&handler);
+        client
+            .create_upload_deferred("http://example.test/uploads", HashMap::new())
+            .unwrap();
+
+        let posts = handler.requests_for(HttpMethod::Post);
+        assert_eq!(
+            posts[0].get(headers::UPLOAD_DEFER_LENGTH),
+            Some(&"1".to_owned())
+        );
+        assert!(!posts[0].contains_key(headers::UPLOAD_LENGTH));
+    }
+
+    #[test]
+    fn create_upload_with_data_returns_location_and_bytes_uploaded() {
+        let handler = FakeHandler::new();
+        handler.script(
+            HttpMethod::Post,
+            response(
+                201,
+                &[
+                    (headers::LOCATION, "http://example.test/uploads/1"),
+                    (headers::UPLOAD_OFFSET, "4"),
+                ],
+            ),
+        );
+
+        let client = Client::new(&handler);
+        let (location, bytes_uploaded) = client
+            .create_upload_with_data("http://example.test/uploads", 10, HashMap::new(), &[1, 2, 3, 4])
+            .unwrap();
+
+        assert_eq!(location, "http://example.test/uploads/1");
+        assert_eq!(bytes_uploaded, 4);
+    }
+
+    #[test]
+    fn terminate_succeeds_on_204_and_maps_404_to_not_found() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Delete, response(204, &[]));
+        handler.script(HttpMethod::Delete, response(404, &[]));
+
+        let client = Client::new(&handler);
+        client.terminate("http://example.test/uploads/1").unwrap();
+        let err = client.terminate("http://example.test/uploads/1").unwrap_err();
+
+        assert!(matches!(err, Error::NotFoundError));
+    }
+
+    #[test]
+    fn create_and_upload_creates_then_uploads_the_file() {
+        let path = std::env::temp_dir().join("tus_client_create_and_upload_test.bin");
+        std::fs::write(&path, [1u8, 2, 3, 4]).unwrap();
+
+        let handler = FakeHandler::new();
+        handler.script(
+            HttpMethod::Post,
+            response(201, &[(headers::LOCATION, "http://example.test/uploads/1")]),
+        );
+        handler.script(
+            HttpMethod::Head,
+            response(
+                200,
+                &[
+                    (headers::UPLOAD_OFFSET, "0"),
+                    (headers::UPLOAD_LENGTH, "4"),
+                ],
+            ),
+        );
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "4")]));
+
+        let client = Client::new(&handler);
+        let location = client
+            .create_and_upload("http://example.test/uploads", &path, HashMap::new())
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(location, "http://example.test/uploads/1");
+        assert_eq!(handler.requests_for(HttpMethod::Patch).len(), 1);
+    }
+
+    #[test]
+    fn upload_reader_errors_when_upload_already_expired() {
+        let handler = FakeHandler::new();
+        handler.script(
+            HttpMethod::Head,
+            response(
+                200,
+                &[
+                    (headers::UPLOAD_OFFSET, "0"),
+                    (headers::UPLOAD_EXPIRES, "Sun, 06 Nov 1994 08:49:37 GMT"),
+                ],
+            ),
+        );
+
+        let client = Client::new(&handler);
+        let data = [1u8; 4];
+        let err = client
+            .upload_reader("http://example.test/upload", &data[..], 8)
+            .unwrap_err();
+
+        assert!(matches!(err, Error::UploadExpired));
+    }
+
+    #[test]
+    fn upload_reader_sends_upload_length_zero_for_an_empty_deferred_length_stream() {
+        let handler = FakeHandler::new();
+        handler.script(HttpMethod::Head, response(200, &[(headers::UPLOAD_OFFSET, "0")]));
+        handler.script(HttpMethod::Patch, response(204, &[(headers::UPLOAD_OFFSET, "0")]));
+
+        let client = Client::new(&handler);
+        let data: &[u8] = &[];
+        client
+            .upload_reader("http://example.test/upload", data, 8)
+            .unwrap();
+
+        let patches = handler.requests_for(HttpMethod::Patch);
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].get(headers::UPLOAD_LENGTH), Some(&"0".to_owned()));
+    }
+}